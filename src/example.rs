@@ -0,0 +1,158 @@
+//! Generates a conforming sample JSON instance from an enforced schema.
+
+use serde_json::{Map, Value};
+
+/// Builds an example value for `node`, resolving `$ref`s against `root`.
+///
+/// Returns `None` when nothing sensible can be produced, e.g. a `$ref` cycle
+/// with no way to bottom out.
+pub(crate) fn build_example(
+    root: &Value,
+    node: &Value,
+    include_optional: bool,
+    visited: &mut Vec<String>,
+) -> Option<Value> {
+    if let Some(reference) = node.get("$ref").and_then(Value::as_str) {
+        if visited.iter().any(|seen| seen == reference) {
+            return None;
+        }
+        let target = lookup_ref(root, reference)?;
+        visited.push(reference.to_string());
+        let example = build_example(root, target, include_optional, visited);
+        visited.pop();
+        return example;
+    }
+
+    if let Some(const_value) = node.get("const") {
+        return Some(const_value.clone());
+    }
+    if let Some(default_value) = node.get("default") {
+        return Some(default_value.clone());
+    }
+    if let Some(values) = node.get("enum").and_then(Value::as_array) {
+        return values.first().cloned();
+    }
+    if let Some(branches) = node.get("anyOf").and_then(Value::as_array) {
+        let branch = branches
+            .iter()
+            .find(|branch| !is_null_schema(branch))
+            .or_else(|| branches.first())?;
+        return build_example(root, branch, include_optional, visited);
+    }
+
+    match primary_type(node).as_deref() {
+        Some("object") => build_object_example(root, node, include_optional, visited),
+        Some("array") => Some(Value::Array(Vec::new())),
+        Some("string") => Some(Value::String(String::new())),
+        Some("integer") | Some("number") => Some(serde_json::json!(0)),
+        Some("boolean") => Some(Value::Bool(false)),
+        Some("null") => Some(Value::Null),
+        _ if node.get("properties").is_some() => build_object_example(root, node, include_optional, visited),
+        _ => Some(Value::Null),
+    }
+}
+
+fn build_object_example(
+    root: &Value,
+    node: &Value,
+    include_optional: bool,
+    visited: &mut Vec<String>,
+) -> Option<Value> {
+    let mut map = Map::new();
+    if let Some(properties) = node.get("properties").and_then(Value::as_object) {
+        for (name, property) in properties {
+            if !include_optional && is_optional(property) {
+                continue;
+            }
+            if let Some(value) = build_example(root, property, include_optional, visited) {
+                if !include_optional && is_empty_object(&value) && is_optional(property) {
+                    continue;
+                }
+                map.insert(name.clone(), value);
+            }
+        }
+    }
+    Some(Value::Object(map))
+}
+
+/// Whether `property` was marked nullable because it was originally optional
+/// (see `transform::MarkOptionalPropertiesNullable`).
+fn is_optional(property: &Value) -> bool {
+    match property.get("type") {
+        Some(Value::Array(types)) => types.iter().any(|value| value == "null"),
+        _ => property
+            .get("anyOf")
+            .and_then(Value::as_array)
+            .is_some_and(|branches| branches.iter().any(is_null_schema)),
+    }
+}
+
+fn is_null_schema(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("null")
+}
+
+fn is_empty_object(value: &Value) -> bool {
+    matches!(value, Value::Object(object) if object.is_empty())
+}
+
+/// The first non-`null` type named by this node's `type` keyword, if any.
+fn primary_type(node: &Value) -> Option<String> {
+    match node.get("type") {
+        Some(Value::String(type_name)) => Some(type_name.clone()),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|type_name| *type_name != "null")
+            .map(String::from),
+        _ => None,
+    }
+}
+
+/// Resolves a local JSON Pointer reference such as `#/$defs/Foo`.
+fn lookup_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    root.pointer(pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_empty_object_is_not_pruned() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "object",
+                    "properties": {
+                        "line2": { "type": ["string", "null"] },
+                    },
+                },
+            },
+        });
+
+        let mut visited = Vec::new();
+        let example = build_example(&schema, &schema, false, &mut visited).expect("example");
+        assert_eq!(example["address"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn optional_empty_object_is_pruned() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "line2": { "type": ["string", "null"] },
+                    },
+                },
+            },
+        });
+
+        let mut visited = Vec::new();
+        let example = build_example(&schema, &schema, false, &mut visited).expect("example");
+        assert!(example.as_object().unwrap().get("address").is_none());
+    }
+}
@@ -16,9 +16,19 @@
 //! println!("{}", serde_json::to_string_pretty(&schema.value).expect("Failed to serialize schema"));
 //! ```
 
+mod example;
+pub mod transform;
+pub mod validate;
+
 pub use schemars;
+pub use transform::Transform;
+pub use validate::{SchemaViolation, SchemaViolationKind};
 
 use schemars::schema_for;
+use transform::{
+    EnforceAllRequiredProperties, InlineRefs, MarkOptionalPropertiesNullable, RemovePropertyFormat,
+    ReplaceOneOfByAnyOf, SetAdditionalPropertiesToFalse,
+};
 
 /// A JSON Schema that is compatible with OpenAI's function calling API.
 pub struct Schema {
@@ -26,112 +36,152 @@ pub struct Schema {
 }
 
 impl Schema {
+    /// Generates a schema for `T`, running the default OpenAI-subset pipeline.
     pub fn new<T: schemars::JsonSchema>() -> Result<Self, serde_json::Error> {
-        let schema = serde_json::to_value(schema_for!(T))?;
-        let mut json_schema = Self { value: schema };
-        json_schema.enforce_openai_subset();
-        Ok(json_schema)
-    }
-
-    fn enforce_openai_subset(&mut self) {
-        Self::remove_property_format_value_from_json(&mut self.value);
-        Self::replace_one_of_by_any_of(&mut self.value);
-        Self::set_additional_properties_to_false(&mut self.value);
-        Self::enforce_all_required_properties(&mut self.value);
-    }
-
-    fn set_additional_properties_to_false(object: &mut serde_json::Value) {
-        match object {
-            serde_json::Value::Object(object) => {
-                if object.get("type") == Some(&serde_json::Value::String("object".into())) {
-                    object.insert("additionalProperties".into(), serde_json::Value::Bool(false));
-                }
-                for value in object.values_mut() {
-                    Self::set_additional_properties_to_false(value);
-                }
-            }
-            serde_json::Value::Array(array) => {
-                for value in array.iter_mut() {
-                    Self::set_additional_properties_to_false(value);
-                }
-            }
-            _ => {}
+        Self::builder().build::<T>()
+    }
+
+    /// Generates a schema for `T`, running the default pipeline followed by
+    /// `extra` custom passes (e.g. injecting vendor `x-` keys or stripping
+    /// specific properties).
+    pub fn new_with_transforms<T: schemars::JsonSchema>(
+        extra: Vec<Box<dyn Transform>>,
+    ) -> Result<Self, serde_json::Error> {
+        let mut builder = Self::builder();
+        for transform in extra {
+            builder = builder.transform(transform);
         }
+        builder.build::<T>()
+    }
+
+    /// Starts a [`SchemaBuilder`] for configuring the pipeline before
+    /// generating a schema.
+    pub fn builder() -> SchemaBuilder {
+        SchemaBuilder::new()
     }
-    
-    fn enforce_all_required_properties(object: &mut serde_json::Value) {
-        match object {
-            serde_json::Value::Object(object) => {
-                let properties = object
-                    .get_mut("properties")
-                    .and_then(|properties| properties.as_object())
-                    .map(|properties|
-                        properties
-                            .keys()
-                            .map(|key| serde_json::Value::String(key.to_string()))
-                            .collect::<Vec<_>>()
-                    );
-                if let (Some(required), Some(properties)) = (object.get_mut("required"), properties) {
-                    if let Some(required) = required.as_array_mut() {
-                        for property in properties {
-                            if !required.contains(&property) {
-                                required.push(property);
-                            }
-                        }
-                    }
-                }
-                for value in object.values_mut() {
-                    Self::enforce_all_required_properties(value);
-                }
-            },
-            serde_json::Value::Array(array) => {
-                for value in array.iter_mut() {
-                    Self::enforce_all_required_properties(value);
-                }
-            }
-            _ => {}
+
+    /// Returns the value of `key` on the root schema object, if present.
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.value.as_object().and_then(|object| object.get(key))
+    }
+
+    /// Inserts `key` into the root schema object, promoting a bare `true`/`false`
+    /// schema to an object first if needed.
+    pub fn insert(&mut self, key: impl Into<String>, value: serde_json::Value) {
+        self.ensure_object();
+        if let serde_json::Value::Object(object) = &mut self.value {
+            object.insert(key.into(), value);
         }
     }
-    
-    fn replace_one_of_by_any_of(object: &mut serde_json::Value) {
-        match object {
-            serde_json::Value::Object(object) => {
-                for key in ["oneOf", "allOf"] {
-                    if object.contains_key(key) {
-                        if let Some(value) = object.remove(key) {
-                            object.insert("anyOf".into(), value);
-                        }
-                    }    
-                }
-                for value in object.values_mut() {
-                    Self::replace_one_of_by_any_of(value);
-                }
-            }
-            serde_json::Value::Array(array) => {
-                for value in array.iter_mut() {
-                    Self::replace_one_of_by_any_of(value);
-                }
-            }
-            _ => {}
+
+    /// Removes and returns `key` from the root schema object, if present.
+    pub fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.value
+            .as_object_mut()
+            .and_then(|object| object.remove(key))
+    }
+
+    fn ensure_object(&mut self) {
+        if !self.value.is_object() {
+            self.value = serde_json::Value::Object(Default::default());
         }
     }
-    
-    fn remove_property_format_value_from_json(object: &mut serde_json::Value) {
-        match object {
-            serde_json::Value::Object(object) => {
-                for key in ["minLength", "maxLength", "pattern", "format", "minimum", "maximum", "multipleOf", "patternProperties", "unevaluatedProperties", "propertyNames", "minProperties", "maxProperties", "unevaluatedItems", "contains", "minContains", "maxContains", "minItems", "maxItems", "uniqueItems"] {
-                    object.remove(key);
-                }
-                for value in object.values_mut() {
-                    Self::remove_property_format_value_from_json(value);
-                }
-            },
-            serde_json::Value::Array(array) => {
-                for value in array.iter_mut() {
-                    Self::remove_property_format_value_from_json(value);
-                }
-            },
-            _ => {}
+
+    /// Builds a conforming sample JSON instance for this schema, including
+    /// optional properties.
+    ///
+    /// Useful for showing end users what a function-call argument payload
+    /// looks like, seeding test fixtures, or priming few-shot prompts.
+    pub fn example(&self) -> serde_json::Value {
+        self.example_with_optional(true)
+    }
+
+    /// Builds a conforming sample JSON instance for this schema, omitting
+    /// optional properties when `include_optional` is `false`. A required
+    /// nested object whose properties are all optional is kept as `{}`.
+    pub fn example_with_optional(&self, include_optional: bool) -> serde_json::Value {
+        let mut visited = Vec::new();
+        example::build_example(&self.value, &self.value, include_optional, &mut visited)
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()))
+    }
+
+    /// Checks this schema against OpenAI's documented structured-output
+    /// limits, returning every violation found rather than stopping at the
+    /// first one.
+    pub fn validate(&self) -> Result<(), Vec<SchemaViolation>> {
+        validate::validate(&self.value)
+    }
+}
+
+/// Configures the pipeline of [`Transform`]s run by [`Schema::new`] and its
+/// variants.
+pub struct SchemaBuilder {
+    nullable_optional_properties: bool,
+    inline_refs: Option<InlineRefs>,
+    extra_transforms: Vec<Box<dyn Transform>>,
+}
+
+impl SchemaBuilder {
+    fn new() -> Self {
+        Self {
+            nullable_optional_properties: true,
+            inline_refs: None,
+            extra_transforms: Vec::new(),
+        }
+    }
+
+    /// Controls whether properties that were not originally required are
+    /// rewritten to permit `null` instead of being forced to a concrete
+    /// value. Enabled by default; disable to restore the old semantics where
+    /// every property is hard-required.
+    pub fn nullable_optional_properties(mut self, enabled: bool) -> Self {
+        self.nullable_optional_properties = enabled;
+        self
+    }
+
+    /// Inlines `$defs`/`definitions` references in place instead of leaving
+    /// the schema split across `$ref` indirection. Off by default. Refs that
+    /// would expand into a cycle are left intact.
+    pub fn inline_refs(mut self) -> Self {
+        self.inline_refs = Some(InlineRefs::new());
+        self
+    }
+
+    /// Like [`SchemaBuilder::inline_refs`], but stops inlining past
+    /// `max_depth` levels, leaving deeper refs intact.
+    pub fn inline_refs_with_max_depth(mut self, max_depth: usize) -> Self {
+        self.inline_refs = Some(InlineRefs::with_max_depth(max_depth));
+        self
+    }
+
+    /// Appends a custom pass to run after the default pipeline.
+    pub fn transform(mut self, transform: Box<dyn Transform>) -> Self {
+        self.extra_transforms.push(transform);
+        self
+    }
+
+    /// Generates a schema for `T`, running the configured pipeline.
+    pub fn build<T: schemars::JsonSchema>(mut self) -> Result<Schema, serde_json::Error> {
+        let value = serde_json::to_value(schema_for!(T))?;
+        let mut schema = Schema { value };
+
+        let mut transforms: Vec<Box<dyn Transform>> = vec![
+            Box::new(RemovePropertyFormat),
+            Box::new(ReplaceOneOfByAnyOf),
+            Box::new(SetAdditionalPropertiesToFalse),
+        ];
+        if self.nullable_optional_properties {
+            transforms.push(Box::new(MarkOptionalPropertiesNullable));
         }
+        transforms.push(Box::new(EnforceAllRequiredProperties));
+        transforms.append(&mut self.extra_transforms);
+        if let Some(inline_refs) = self.inline_refs {
+            transforms.push(Box::new(inline_refs));
+        }
+
+        for transform in &mut transforms {
+            transform.transform(&mut schema.value);
+        }
+        Ok(schema)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,604 @@
+//! Individual rewrite passes applied to a generated JSON Schema, plus the
+//! [`Transform`] trait that lets callers add their own.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// A single rewrite pass over a JSON Schema document.
+///
+/// Implementors describe a *local* edit to the schema node they are given and
+/// call [`Transform::transform_subschemas`] to recurse into the standard
+/// subschema locations (`properties.*`, `items`, `prefixItems`, the
+/// `anyOf`/`allOf`/`oneOf` arrays, `$defs`/`definitions.*`, and an object
+/// `additionalProperties`) rather than re-implementing that traversal
+/// themselves.
+pub trait Transform {
+    /// Apply this pass to `schema`, recursing into subschemas as needed.
+    fn transform(&mut self, schema: &mut Value);
+
+    /// Recurse into the standard subschema locations of `schema`, applying
+    /// `self` to each one found.
+    fn transform_subschemas(&mut self, schema: &mut Value) {
+        let Value::Object(object) = schema else {
+            return;
+        };
+
+        if let Some(properties) = object.get_mut("properties").and_then(Value::as_object_mut) {
+            for value in properties.values_mut() {
+                self.transform(value);
+            }
+        }
+
+        if let Some(items) = object.get_mut("items") {
+            self.transform(items);
+        }
+
+        if let Some(prefix_items) = object.get_mut("prefixItems").and_then(Value::as_array_mut) {
+            for value in prefix_items {
+                self.transform(value);
+            }
+        }
+
+        for key in ["anyOf", "allOf", "oneOf"] {
+            if let Some(values) = object.get_mut(key).and_then(Value::as_array_mut) {
+                for value in values {
+                    self.transform(value);
+                }
+            }
+        }
+
+        for key in ["$defs", "definitions"] {
+            if let Some(defs) = object.get_mut(key).and_then(Value::as_object_mut) {
+                for value in defs.values_mut() {
+                    self.transform(value);
+                }
+            }
+        }
+
+        if let Some(additional_properties) =
+            object.get_mut("additionalProperties").filter(|value| value.is_object())
+        {
+            self.transform(additional_properties);
+        }
+    }
+}
+
+/// Keywords that OpenAI's structured output subset does not support, removed
+/// wholesale by [`RemovePropertyFormat`].
+pub(crate) const FORBIDDEN_KEYWORDS: &[&str] = &[
+    "minLength",
+    "maxLength",
+    "pattern",
+    "format",
+    "minimum",
+    "maximum",
+    "multipleOf",
+    "patternProperties",
+    "unevaluatedProperties",
+    "propertyNames",
+    "minProperties",
+    "maxProperties",
+    "unevaluatedItems",
+    "contains",
+    "minContains",
+    "maxContains",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+];
+
+/// Strips keywords that OpenAI's structured output subset does not support.
+#[derive(Debug, Default)]
+pub struct RemovePropertyFormat;
+
+impl Transform for RemovePropertyFormat {
+    fn transform(&mut self, schema: &mut Value) {
+        if let Value::Object(object) = schema {
+            for key in FORBIDDEN_KEYWORDS {
+                object.remove(*key);
+            }
+        }
+        self.transform_subschemas(schema);
+    }
+}
+
+/// Replaces `oneOf`/`allOf` with `anyOf`, which is the only union keyword
+/// OpenAI's subset understands.
+#[derive(Debug, Default)]
+pub struct ReplaceOneOfByAnyOf;
+
+impl Transform for ReplaceOneOfByAnyOf {
+    fn transform(&mut self, schema: &mut Value) {
+        if let Value::Object(object) = schema {
+            for key in ["oneOf", "allOf"] {
+                if let Some(value) = object.remove(key) {
+                    object.insert("anyOf".into(), value);
+                }
+            }
+        }
+        self.transform_subschemas(schema);
+    }
+}
+
+/// Sets `additionalProperties: false` on every schema whose `type` is the
+/// string `"object"`, as required by OpenAI's strict structured output mode.
+/// A schema with a `type` array such as `["object", "null"]` is not changed.
+#[derive(Debug, Default)]
+pub struct SetAdditionalPropertiesToFalse;
+
+impl Transform for SetAdditionalPropertiesToFalse {
+    fn transform(&mut self, schema: &mut Value) {
+        match schema {
+            Value::Object(object) if object.get("type") == Some(&Value::String("object".into())) => {
+                object.insert("additionalProperties".into(), Value::Bool(false));
+            }
+            _ => {}
+        }
+        self.transform_subschemas(schema);
+    }
+}
+
+/// Forces every declared property into `required`, which OpenAI's strict
+/// mode demands.
+#[derive(Debug, Default)]
+pub struct EnforceAllRequiredProperties;
+
+impl Transform for EnforceAllRequiredProperties {
+    fn transform(&mut self, schema: &mut Value) {
+        if let Value::Object(object) = schema {
+            let properties = object
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|properties| {
+                    properties
+                        .keys()
+                        .map(|key| Value::String(key.to_string()))
+                        .collect::<Vec<_>>()
+                });
+            if let Some(properties) = properties {
+                match object.get_mut("required") {
+                    Some(Value::Array(required)) => {
+                        for property in properties {
+                            if !required.contains(&property) {
+                                required.push(property);
+                            }
+                        }
+                    }
+                    _ => {
+                        object.insert("required".into(), Value::Array(properties));
+                    }
+                }
+            }
+        }
+        self.transform_subschemas(schema);
+    }
+}
+
+/// Rewrites properties that were not originally in `required` so their type
+/// permits `null`, before [`EnforceAllRequiredProperties`] forces every
+/// property into `required` for OpenAI's strict mode.
+///
+/// This preserves the meaning of a Rust `Option<T>` field: the model still
+/// must mention the key (satisfying the subset), but may set it to `null`
+/// instead of being told it must always have a concrete value.
+#[derive(Debug, Default)]
+pub struct MarkOptionalPropertiesNullable;
+
+impl Transform for MarkOptionalPropertiesNullable {
+    fn transform(&mut self, schema: &mut Value) {
+        if let Value::Object(object) = schema {
+            let required: std::collections::HashSet<String> = object
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|required| {
+                    required
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            if let Some(properties) = object.get_mut("properties").and_then(Value::as_object_mut) {
+                for (name, property) in properties.iter_mut() {
+                    if !required.contains(name.as_str()) {
+                        Self::make_nullable(property);
+                    }
+                }
+            }
+        }
+        self.transform_subschemas(schema);
+    }
+}
+
+impl MarkOptionalPropertiesNullable {
+    fn make_nullable(property: &mut Value) {
+        let Value::Object(object) = property else {
+            return;
+        };
+
+        if let Some(any_of) = object.get_mut("anyOf").and_then(Value::as_array_mut) {
+            let already_nullable = any_of
+                .iter()
+                .any(|branch| branch.get("type") == Some(&Value::String("null".into())));
+            if !already_nullable {
+                any_of.push(serde_json::json!({ "type": "null" }));
+            }
+            return;
+        }
+
+        if let Some(reference) = object.remove("$ref") {
+            object.insert(
+                "anyOf".into(),
+                Value::Array(vec![
+                    serde_json::json!({ "$ref": reference }),
+                    serde_json::json!({ "type": "null" }),
+                ]),
+            );
+            return;
+        }
+
+        match object.get_mut("type") {
+            Some(Value::String(type_name)) => {
+                let type_name = std::mem::take(type_name);
+                object.insert("type".into(), serde_json::json!([type_name, "null"]));
+            }
+            Some(Value::Array(types)) if !types.iter().any(|value| value == "null") => {
+                types.push(Value::String("null".into()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Inlines `#/$defs/...` and `#/definitions/...` references in place and
+/// drops the now-unused definitions block, producing a single
+/// self-contained schema.
+///
+/// Off by default; opt in via [`crate::SchemaBuilder::inline_refs`]. A ref
+/// that would expand into a cycle (directly or transitively self-referential
+/// types), or that would exceed an optional max inline depth, is left
+/// intact instead of being inlined.
+#[derive(Debug, Default)]
+pub struct InlineRefs {
+    max_depth: Option<usize>,
+}
+
+impl InlineRefs {
+    /// Inlines refs with no depth limit beyond cycle detection.
+    pub fn new() -> Self {
+        Self { max_depth: None }
+    }
+
+    /// Inlines refs up to `max_depth` levels deep, leaving deeper refs intact.
+    pub fn with_max_depth(max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+        }
+    }
+}
+
+impl Transform for InlineRefs {
+    fn transform(&mut self, schema: &mut Value) {
+        let defs = Self::collect_defs(schema);
+        let mut stack = Vec::new();
+        Self::inline(schema, &defs, &mut stack, self.max_depth);
+
+        // A cycle or a depth cap can leave some `$ref`s unresolved; keep only
+        // the definitions those still point to instead of dropping the
+        // section outright, which would leave the refs dangling.
+        let still_referenced = Self::referenced_def_names(schema);
+        if let Value::Object(object) = schema {
+            for key in ["$defs", "definitions"] {
+                let Some(section) = object.get_mut(key).and_then(Value::as_object_mut) else {
+                    continue;
+                };
+                section.retain(|name, _| still_referenced.contains(name));
+                if section.is_empty() {
+                    object.remove(key);
+                }
+            }
+        }
+    }
+}
+
+impl InlineRefs {
+    fn collect_defs(schema: &Value) -> HashMap<String, Value> {
+        let mut defs = HashMap::new();
+        if let Value::Object(object) = schema {
+            for key in ["$defs", "definitions"] {
+                if let Some(section) = object.get(key).and_then(Value::as_object) {
+                    for (name, value) in section {
+                        defs.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        defs
+    }
+
+    fn referenced_def_names(schema: &Value) -> std::collections::HashSet<String> {
+        let mut names = std::collections::HashSet::new();
+        Self::collect_referenced_def_names(schema, &mut names);
+        names
+    }
+
+    fn collect_referenced_def_names(node: &Value, names: &mut std::collections::HashSet<String>) {
+        match node {
+            Value::Object(object) => {
+                let is_def_ref = object.get("$ref").and_then(Value::as_str).filter(|reference| {
+                    reference.starts_with("#/$defs/") || reference.starts_with("#/definitions/")
+                });
+                if let Some(name) = is_def_ref.and_then(|reference| reference.rsplit('/').next()) {
+                    names.insert(name.to_string());
+                }
+                for value in object.values() {
+                    Self::collect_referenced_def_names(value, names);
+                }
+            }
+            Value::Array(array) => {
+                for value in array {
+                    Self::collect_referenced_def_names(value, names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn inline(
+        node: &mut Value,
+        defs: &HashMap<String, Value>,
+        stack: &mut Vec<String>,
+        max_depth: Option<usize>,
+    ) {
+        let ref_name = match &*node {
+            Value::Object(object) => object.get("$ref").and_then(Value::as_str).and_then(|reference| {
+                let is_def_ref =
+                    reference.starts_with("#/$defs/") || reference.starts_with("#/definitions/");
+                is_def_ref.then(|| reference.rsplit('/').next().unwrap().to_string())
+            }),
+            _ => None,
+        };
+
+        if let Some(name) = ref_name {
+            let is_cycle = stack.contains(&name);
+            let depth_exceeded = max_depth.is_some_and(|max| stack.len() >= max);
+            let target = (!is_cycle && !depth_exceeded)
+                .then(|| defs.get(&name))
+                .flatten();
+            if let Some(target) = target {
+                let mut inlined = target.clone();
+                if let (Value::Object(object), Value::Object(inlined_object)) = (&*node, &mut inlined) {
+                    for (key, value) in object {
+                        if key != "$ref" {
+                            inlined_object.entry(key.clone()).or_insert_with(|| value.clone());
+                        }
+                    }
+                }
+                *node = inlined;
+                stack.push(name);
+                Self::inline(node, defs, stack, max_depth);
+                stack.pop();
+            }
+            return;
+        }
+
+        match node {
+            // Don't descend into `$defs`/`definitions` directly: a kept
+            // (cycle- or depth-capped) definition should stay exactly as
+            // generated, and is still reachable for inlining through any
+            // `$ref` that points at it.
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    if key == "$defs" || key == "definitions" {
+                        continue;
+                    }
+                    Self::inline(value, defs, stack, max_depth);
+                }
+            }
+            Value::Array(array) => {
+                for value in array.iter_mut() {
+                    Self::inline(value, defs, stack, max_depth);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enforce_all_required_properties_adds_required_when_absent() {
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": ["string", "null"] },
+                "b": { "type": ["integer", "null"] },
+            },
+        });
+
+        EnforceAllRequiredProperties.transform(&mut schema);
+
+        let required = schema["required"].as_array().expect("required array");
+        let mut required: Vec<&str> = required.iter().filter_map(Value::as_str).collect();
+        required.sort_unstable();
+        assert_eq!(required, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn make_nullable_rewrites_a_plain_type_string() {
+        let mut property = serde_json::json!({ "type": "string" });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(property, serde_json::json!({ "type": ["string", "null"] }));
+    }
+
+    #[test]
+    fn make_nullable_appends_to_an_existing_type_array() {
+        let mut property = serde_json::json!({ "type": ["string", "integer"] });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(property["type"], serde_json::json!(["string", "integer", "null"]));
+    }
+
+    #[test]
+    fn make_nullable_is_idempotent_on_an_already_nullable_type_array() {
+        let mut property = serde_json::json!({ "type": ["string", "null"] });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(property["type"], serde_json::json!(["string", "null"]));
+    }
+
+    #[test]
+    fn make_nullable_wraps_a_ref_in_any_of() {
+        let mut property = serde_json::json!({ "$ref": "#/definitions/Inner" });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(
+            property,
+            serde_json::json!({
+                "anyOf": [
+                    { "$ref": "#/definitions/Inner" },
+                    { "type": "null" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn make_nullable_appends_to_an_existing_any_of() {
+        let mut property = serde_json::json!({
+            "anyOf": [{ "type": "string" }, { "type": "integer" }],
+        });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(
+            property["anyOf"],
+            serde_json::json!([{ "type": "string" }, { "type": "integer" }, { "type": "null" }])
+        );
+    }
+
+    #[test]
+    fn make_nullable_is_idempotent_on_an_already_nullable_any_of() {
+        let mut property = serde_json::json!({
+            "anyOf": [{ "type": "string" }, { "type": "null" }],
+        });
+        MarkOptionalPropertiesNullable::make_nullable(&mut property);
+        assert_eq!(
+            property["anyOf"],
+            serde_json::json!([{ "type": "string" }, { "type": "null" }])
+        );
+    }
+
+    #[test]
+    fn mark_optional_properties_nullable_skips_required_properties() {
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "nickname": { "type": "string" },
+            },
+            "required": ["name"],
+        });
+
+        MarkOptionalPropertiesNullable.transform(&mut schema);
+
+        assert_eq!(schema["properties"]["name"]["type"], serde_json::json!("string"));
+        assert_eq!(
+            schema["properties"]["nickname"]["type"],
+            serde_json::json!(["string", "null"])
+        );
+    }
+
+    #[test]
+    fn inline_refs_replaces_a_non_cyclic_ref_in_place() {
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": { "address": { "$ref": "#/definitions/Address" } },
+            "required": ["address"],
+            "definitions": {
+                "Address": {
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                    "required": ["city"],
+                },
+            },
+        });
+
+        InlineRefs::new().transform(&mut schema);
+
+        assert_eq!(
+            schema["properties"]["address"],
+            serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"],
+            })
+        );
+        assert!(schema.get("definitions").is_none());
+    }
+
+    #[test]
+    fn inline_refs_leaves_a_cycle_as_a_flat_self_ref() {
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": { "next": { "$ref": "#/definitions/Node" } },
+            "required": ["next"],
+            "definitions": {
+                "Node": {
+                    "type": "object",
+                    "properties": { "next": { "$ref": "#/definitions/Node" } },
+                    "required": ["next"],
+                },
+            },
+        });
+
+        InlineRefs::new().transform(&mut schema);
+
+        // The root's own usage is inlined one level, with the cycle left as
+        // an unexpanded $ref.
+        assert_eq!(
+            schema["properties"]["next"]["properties"]["next"],
+            serde_json::json!({ "$ref": "#/definitions/Node" })
+        );
+        // The kept definition is retained exactly as generated, not
+        // partially unfolded against itself.
+        assert_eq!(
+            schema["definitions"]["Node"],
+            serde_json::json!({
+                "type": "object",
+                "properties": { "next": { "$ref": "#/definitions/Node" } },
+                "required": ["next"],
+            })
+        );
+    }
+
+    #[test]
+    fn inline_refs_with_max_depth_stops_at_the_limit() {
+        let mut schema = serde_json::json!({
+            "type": "object",
+            "properties": { "a": { "$ref": "#/definitions/A" } },
+            "required": ["a"],
+            "definitions": {
+                "A": {
+                    "type": "object",
+                    "properties": { "b": { "$ref": "#/definitions/B" } },
+                    "required": ["b"],
+                },
+                "B": {
+                    "type": "object",
+                    "properties": { "value": { "type": "string" } },
+                    "required": ["value"],
+                },
+            },
+        });
+
+        InlineRefs::with_max_depth(1).transform(&mut schema);
+
+        assert_eq!(
+            schema["properties"]["a"]["properties"]["b"],
+            serde_json::json!({ "$ref": "#/definitions/B" })
+        );
+        assert!(schema["definitions"].get("B").is_some());
+        assert!(schema["definitions"].get("A").is_none());
+    }
+}
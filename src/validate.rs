@@ -0,0 +1,381 @@
+//! Checks a schema against OpenAI's documented structured-output limits.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::transform::FORBIDDEN_KEYWORDS;
+
+/// Maximum number of object properties across the whole schema.
+pub const MAX_TOTAL_PROPERTIES: usize = 100;
+/// Maximum nesting depth of an object schema below the root. Every subschema
+/// step counts as one level: a property, `items`/`prefixItems`, an
+/// `anyOf`/`allOf`/`oneOf` branch, or an object `additionalProperties`.
+pub const MAX_NESTING_DEPTH: usize = 5;
+/// Maximum number of enum values across the whole schema.
+pub const MAX_ENUM_VALUES: usize = 500;
+
+/// A single way a schema fails to satisfy OpenAI's structured-output subset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending node, e.g. `/properties/address`.
+    pub path: String,
+    pub kind: SchemaViolationKind,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+/// The specific limit or subset rule a [`SchemaViolation`] breaks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaViolationKind {
+    /// More object properties in total than OpenAI allows.
+    TooManyProperties { count: usize, limit: usize },
+    /// Object nesting deeper than OpenAI allows.
+    NestingTooDeep { depth: usize, limit: usize },
+    /// More enum values in total than OpenAI allows.
+    TooManyEnumValues { count: usize, limit: usize },
+    /// A keyword present that the OpenAI subset does not support.
+    ForbiddenKeyword { keyword: String },
+    /// An object schema without `additionalProperties: false`.
+    MissingAdditionalPropertiesFalse,
+    /// An object schema whose `required` list omits some of its `properties`.
+    IncompleteRequired { missing: Vec<String> },
+}
+
+impl std::fmt::Display for SchemaViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyProperties { count, limit } => {
+                write!(f, "{count} object properties exceeds the limit of {limit}")
+            }
+            Self::NestingTooDeep { depth, limit } => {
+                write!(f, "nesting depth {depth} exceeds the limit of {limit}")
+            }
+            Self::TooManyEnumValues { count, limit } => {
+                write!(f, "{count} enum values exceeds the limit of {limit}")
+            }
+            Self::ForbiddenKeyword { keyword } => {
+                write!(f, "keyword `{keyword}` is not supported by the OpenAI subset")
+            }
+            Self::MissingAdditionalPropertiesFalse => {
+                write!(f, "object is missing `additionalProperties: false`")
+            }
+            Self::IncompleteRequired { missing } => {
+                write!(f, "`required` is missing properties: {}", missing.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    total_properties: usize,
+    total_enum_values: usize,
+    max_depth: usize,
+    deepest_path: String,
+}
+
+pub(crate) fn validate(schema: &Value) -> Result<(), Vec<SchemaViolation>> {
+    let mut accumulator = Accumulator::default();
+    let mut violations = Vec::new();
+    let mut resolved_refs = HashSet::new();
+    walk(schema, schema, "", 0, &mut resolved_refs, &mut accumulator, &mut violations);
+
+    if accumulator.total_properties > MAX_TOTAL_PROPERTIES {
+        violations.push(SchemaViolation {
+            path: String::new(),
+            kind: SchemaViolationKind::TooManyProperties {
+                count: accumulator.total_properties,
+                limit: MAX_TOTAL_PROPERTIES,
+            },
+        });
+    }
+    if accumulator.total_enum_values > MAX_ENUM_VALUES {
+        violations.push(SchemaViolation {
+            path: String::new(),
+            kind: SchemaViolationKind::TooManyEnumValues {
+                count: accumulator.total_enum_values,
+                limit: MAX_ENUM_VALUES,
+            },
+        });
+    }
+    if accumulator.max_depth > MAX_NESTING_DEPTH {
+        violations.push(SchemaViolation {
+            path: accumulator.deepest_path,
+            kind: SchemaViolationKind::NestingTooDeep {
+                depth: accumulator.max_depth,
+                limit: MAX_NESTING_DEPTH,
+            },
+        });
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn walk(
+    root: &Value,
+    node: &Value,
+    path: &str,
+    depth: usize,
+    resolved_refs: &mut HashSet<String>,
+    accumulator: &mut Accumulator,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    let Value::Object(object) = node else {
+        return;
+    };
+
+    // Resolve `$ref`s in place, at the depth of the slot they occupy, so
+    // nesting through `$defs`/`definitions` indirection (the common case for
+    // derived structs) is measured the same as inline nesting. Each distinct
+    // ref is only ever walked once: this both stops a ref cycle (the target
+    // is already marked resolved by the time its own `$ref` loops back) and
+    // stops a definition referenced from multiple places in the schema from
+    // contributing its property/enum counts more than once.
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        if !resolved_refs.insert(reference.to_string()) {
+            return;
+        }
+        if let Some(target) = root.pointer(reference.strip_prefix('#').unwrap_or(reference)) {
+            walk(root, target, path, depth, resolved_refs, accumulator, violations);
+        }
+        return;
+    }
+
+    for keyword in FORBIDDEN_KEYWORDS {
+        if object.contains_key(*keyword) {
+            violations.push(SchemaViolation {
+                path: path.to_string(),
+                kind: SchemaViolationKind::ForbiddenKeyword {
+                    keyword: keyword.to_string(),
+                },
+            });
+        }
+    }
+
+    if let Some(enum_values) = object.get("enum").and_then(Value::as_array) {
+        accumulator.total_enum_values += enum_values.len();
+    }
+
+    let properties = object.get("properties").and_then(Value::as_object);
+    let is_object_schema =
+        object.get("type") == Some(&Value::String("object".into())) || properties.is_some();
+
+    if is_object_schema {
+        if depth > accumulator.max_depth {
+            accumulator.max_depth = depth;
+            accumulator.deepest_path = path.to_string();
+        }
+
+        let property_names: Vec<String> = properties
+            .map(|properties| properties.keys().cloned().collect())
+            .unwrap_or_default();
+        accumulator.total_properties += property_names.len();
+
+        if object.get("additionalProperties") != Some(&Value::Bool(false)) {
+            violations.push(SchemaViolation {
+                path: path.to_string(),
+                kind: SchemaViolationKind::MissingAdditionalPropertiesFalse,
+            });
+        }
+
+        let required: HashSet<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let missing: Vec<String> = property_names
+            .iter()
+            .filter(|name| !required.contains(name.as_str()))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            violations.push(SchemaViolation {
+                path: path.to_string(),
+                kind: SchemaViolationKind::IncompleteRequired { missing },
+            });
+        }
+
+        if let Some(properties) = properties {
+            for (name, child) in properties {
+                walk(
+                    root,
+                    child,
+                    &format!("{path}/properties/{name}"),
+                    depth + 1,
+                    resolved_refs,
+                    accumulator,
+                    violations,
+                );
+            }
+        }
+    }
+
+    if let Some(items) = object.get("items") {
+        walk(root, items, &format!("{path}/items"), depth + 1, resolved_refs, accumulator, violations);
+    }
+    if let Some(prefix_items) = object.get("prefixItems").and_then(Value::as_array) {
+        for (index, item) in prefix_items.iter().enumerate() {
+            walk(
+                root,
+                item,
+                &format!("{path}/prefixItems/{index}"),
+                depth + 1,
+                resolved_refs,
+                accumulator,
+                violations,
+            );
+        }
+    }
+    for key in ["anyOf", "allOf", "oneOf"] {
+        if let Some(values) = object.get(key).and_then(Value::as_array) {
+            for (index, value) in values.iter().enumerate() {
+                walk(
+                    root,
+                    value,
+                    &format!("{path}/{key}/{index}"),
+                    depth + 1,
+                    resolved_refs,
+                    accumulator,
+                    violations,
+                );
+            }
+        }
+    }
+    if let Some(additional_properties) =
+        object.get("additionalProperties").filter(|value| value.is_object())
+    {
+        walk(
+            root,
+            additional_properties,
+            &format!("{path}/additionalProperties"),
+            depth + 1,
+            resolved_refs,
+            accumulator,
+            violations,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_through_refs_counts_toward_depth() {
+        // A chain of 6 structs, each nested only via `$ref`/`definitions`,
+        // as schemars emits for named struct fields.
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "a0": { "$ref": "#/definitions/A1" } },
+            "required": ["a0"],
+            "additionalProperties": false,
+            "definitions": {
+                "A1": {
+                    "type": "object",
+                    "properties": { "a1": { "$ref": "#/definitions/A2" } },
+                    "required": ["a1"],
+                    "additionalProperties": false,
+                },
+                "A2": {
+                    "type": "object",
+                    "properties": { "a2": { "$ref": "#/definitions/A3" } },
+                    "required": ["a2"],
+                    "additionalProperties": false,
+                },
+                "A3": {
+                    "type": "object",
+                    "properties": { "a3": { "$ref": "#/definitions/A4" } },
+                    "required": ["a3"],
+                    "additionalProperties": false,
+                },
+                "A4": {
+                    "type": "object",
+                    "properties": { "a4": { "$ref": "#/definitions/A5" } },
+                    "required": ["a4"],
+                    "additionalProperties": false,
+                },
+                "A5": {
+                    "type": "object",
+                    "properties": { "a5": { "$ref": "#/definitions/A6" } },
+                    "required": ["a5"],
+                    "additionalProperties": false,
+                },
+                "A6": {
+                    "type": "object",
+                    "properties": {},
+                    "required": [],
+                    "additionalProperties": false,
+                },
+            },
+        });
+
+        let violations = validate(&schema).expect_err("should exceed MAX_NESTING_DEPTH");
+        assert!(violations.iter().any(|violation| matches!(
+            violation.kind,
+            SchemaViolationKind::NestingTooDeep { .. }
+        )));
+    }
+
+    #[test]
+    fn ref_cycle_does_not_infinite_loop() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "next": { "$ref": "#/definitions/Node" } },
+            "required": ["next"],
+            "additionalProperties": false,
+            "definitions": {
+                "Node": {
+                    "type": "object",
+                    "properties": { "next": { "$ref": "#/definitions/Node" } },
+                    "required": ["next"],
+                    "additionalProperties": false,
+                },
+            },
+        });
+
+        let _ = validate(&schema);
+    }
+
+    #[test]
+    fn shared_ref_is_only_counted_once() {
+        // Two fields of the same 55-property type: 57 distinct properties in
+        // total (2 top-level + 55 in the shared type), well under the limit.
+        let mut big_properties = serde_json::Map::new();
+        for index in 0..55 {
+            big_properties.insert(
+                format!("field{index}"),
+                serde_json::json!({ "type": ["string", "null"] }),
+            );
+        }
+        let required: Vec<Value> = big_properties.keys().map(|name| Value::String(name.clone())).collect();
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "a": { "$ref": "#/definitions/Big" },
+                "b": { "$ref": "#/definitions/Big" },
+            },
+            "required": ["a", "b"],
+            "additionalProperties": false,
+            "definitions": {
+                "Big": {
+                    "type": "object",
+                    "properties": big_properties,
+                    "required": required,
+                    "additionalProperties": false,
+                },
+            },
+        });
+
+        assert_eq!(validate(&schema), Ok(()));
+    }
+}